@@ -0,0 +1,34 @@
+use chrono::{Duration, Local, NaiveDate};
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(about = "Check upcoming bin collections and send a reminder notification")]
+pub struct Cli {
+    /// Start the lookahead window on a specific date instead of tomorrow (YYYY-MM-DD)
+    #[arg(long)]
+    pub date: Option<NaiveDate>,
+
+    /// Number of days from the start date to include in the lookahead window
+    #[arg(long, default_value_t = 1)]
+    pub days_ahead: i64,
+
+    /// Read schedule data from a saved page instead of querying the lookup URL
+    #[arg(long)]
+    pub input_file: Option<PathBuf>,
+
+    /// Print the resolved message instead of sending a notification
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Emit the schedule as an iCalendar feed; pass a file path, or omit one to print to stdout
+    #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "-")]
+    pub ical_output: Option<PathBuf>,
+}
+
+impl Cli {
+    pub fn start_date(&self) -> NaiveDate {
+        self.date
+            .unwrap_or_else(|| Local::now().date_naive() + Duration::days(1))
+    }
+}
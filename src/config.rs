@@ -0,0 +1,23 @@
+use crate::notifier::NotifierKind;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub lookup_url: String,
+    pub address_code: String,
+    pub notification_url: String,
+    pub notification_priority: String,
+    #[serde(default)]
+    pub notifier: NotifierKind,
+    pub bins: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Config, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+}
@@ -1,6 +1,23 @@
+mod cli;
+mod config;
+mod notifier;
+
 use chrono::{Duration, Local, NaiveDate};
-use reqwest::blocking::{multipart, Client};
+use clap::Parser;
+use cli::Cli;
+use config::Config;
+use icalendar::{Alarm, Calendar, Component, Event, EventLike};
+use notifier::{build_notifier, Notifier};
+use reqwest::blocking::Client;
+use std::collections::BTreeMap;
 use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+const CONFIG_PATH: &str = "config.toml";
+
+/// Bin collections grouped by date, each date carrying every bin due that day.
+type Upcoming = Vec<(NaiveDate, Vec<String>)>;
 
 fn initiate_client() -> Result<Client, reqwest::Error> {
     Client::builder()
@@ -8,9 +25,8 @@ fn initiate_client() -> Result<Client, reqwest::Error> {
         .build()
 }
 
-fn query_bin_data(client: Client) -> Result<String, Box<dyn Error>> {
-    let url = env!("LOOKUP_URL");
-    Ok(client.get(url).send()?.text()?)
+fn query_bin_data(client: Client, config: &Config) -> Result<String, Box<dyn Error>> {
+    Ok(client.get(&config.lookup_url).send()?.text()?)
 }
 
 fn get_coded_pair(chunk: &[char]) -> Result<(String, char), Box<dyn Error>> {
@@ -40,31 +56,33 @@ fn get_coded_pairs(coded_data: String) -> Result<Vec<(String, char)>, Box<dyn Er
         .collect()
 }
 
-fn get_schedule(schedule_string: String) -> Result<Vec<(NaiveDate, String)>, Box<dyn Error>> {
-    get_coded_schedule(schedule_string)?
+fn get_schedule(
+    schedule_string: String,
+    config: &Config,
+) -> Result<Vec<(NaiveDate, String)>, Box<dyn Error>> {
+    get_coded_schedule(schedule_string, config)?
         .into_iter()
-        .map(decode_data)
+        .map(|coded_data| decode_data(coded_data, config))
         .collect()
 }
 
-fn get_coded_schedule(text_result: String) -> Result<Vec<(String, char)>, Box<dyn Error>> {
-    let address_code = env!("ADDRESS_CODE");
-
+fn get_coded_schedule(
+    text_result: String,
+    config: &Config,
+) -> Result<Vec<(String, char)>, Box<dyn Error>> {
     for line in text_result.lines() {
-        if line.starts_with(address_code) {
+        if line.starts_with(&config.address_code) {
             return get_coded_pairs(line.to_owned());
         }
     }
     Err("No result found for specified property")?
 }
 
-fn format_bin(bin_code: char) -> String {
-    return match bin_code {
-        'B' => "Black Bin".to_owned(),
-        'G' => "Green Bin".to_owned(),
-        'R' => "Brown Bin".to_owned(),
-        other => format!("Unknown Bin '{}'", other).to_owned(),
-    };
+fn format_bin(bin_code: char, config: &Config) -> String {
+    match config.bins.get(&bin_code.to_string()) {
+        Some(name) => name.to_owned(),
+        None => format!("Unknown Bin '{}'", bin_code),
+    }
 }
 
 fn decode_date(coded_date: String) -> Result<NaiveDate, Box<dyn Error>> {
@@ -80,72 +98,231 @@ fn decode_date(coded_date: String) -> Result<NaiveDate, Box<dyn Error>> {
     Ok(formatted_date)
 }
 
-fn decode_data(coded_data: (String, char)) -> Result<(NaiveDate, String), Box<dyn Error>> {
+fn decode_data(
+    coded_data: (String, char),
+    config: &Config,
+) -> Result<(NaiveDate, String), Box<dyn Error>> {
     let (coded_date, bin_code) = coded_data;
 
     let decoded_date = decode_date(coded_date)?;
-    let bin = format_bin(bin_code);
+    let bin = format_bin(bin_code, config);
 
     Ok((decoded_date, bin))
 }
 
-fn get_tomorrows_bin(schedule: Vec<(NaiveDate, String)>) -> Option<String> {
-    let date_tomorrow = Local::now().date_naive() + Duration::days(1);
+fn get_upcoming_bins(schedule: Vec<(NaiveDate, String)>, start: NaiveDate, days: i64) -> Upcoming {
+    let end = start + Duration::days(days.max(1));
+    let mut grouped: BTreeMap<NaiveDate, Vec<String>> = BTreeMap::new();
 
     for (date, bin) in schedule {
-        if date == date_tomorrow {
-            return Some(bin);
+        if date >= start && date < end {
+            grouped.entry(date).or_default().push(bin);
         }
     }
-    None
+
+    grouped.into_iter().collect()
+}
+
+fn format_bin_list(bins: &[String]) -> String {
+    match bins.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+fn format_upcoming_message(upcoming: &[(NaiveDate, Vec<String>)]) -> Option<String> {
+    if upcoming.is_empty() {
+        return None;
+    }
+
+    let tomorrow = Local::now().date_naive() + Duration::days(1);
+
+    let lines: Vec<String> = upcoming
+        .iter()
+        .map(|(date, bins)| {
+            if *date == tomorrow {
+                format!("Put out {} for tomorrow", format_bin_list(bins))
+            } else {
+                format!("Put out {} on {}", format_bin_list(bins), date.format("%A %-d %B"))
+            }
+        })
+        .collect();
+
+    Some(lines.join("\n"))
+}
+
+fn build_event(date: &NaiveDate, bin: &str) -> Event {
+    let uid = format!("{}-{}@bin-reminder", date.format("%Y%m%d"), bin.replace(' ', "_"));
+
+    let reminder = Alarm::display(
+        &format!("{} collection tomorrow", bin),
+        -Duration::hours(6), //triggers at 18:00 the evening before
+    );
+
+    Event::new()
+        .uid(&uid)
+        .summary(&format!("{} collection", bin))
+        .all_day(*date)
+        .alarm(reminder)
+        .done()
+}
+
+fn build_ical_feed(schedule: &[(NaiveDate, String)]) -> String {
+    let mut calendar = Calendar::new();
+    calendar.name("Bin Collection Schedule");
+
+    for (date, bin) in schedule {
+        calendar.push(build_event(date, bin));
+    }
+
+    calendar.done().to_string()
 }
 
-fn get_bin(client: Client) -> Result<Option<String>, Box<dyn Error>> {
-    let site_response = query_bin_data(client)?;
-    let schedule = get_schedule(site_response)?;
-    Ok(get_tomorrows_bin(schedule))
+fn write_ical_feed(ics: &str, output_path: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    match output_path {
+        Some(path) => fs::write(path, ics)?,
+        None => println!("{}", ics),
+    }
+    Ok(())
 }
 
-fn send_notification(client: Client, message: String) {
-    let url = env!("NOTIFICATION_URL");
+fn fetch_schedule(
+    client: Client,
+    config: &Config,
+    cli: &Cli,
+) -> Result<Vec<(NaiveDate, String)>, Box<dyn Error>> {
+    let schedule_string = match &cli.input_file {
+        Some(path) => fs::read_to_string(path)?,
+        None => query_bin_data(client, config)?,
+    };
+    get_schedule(schedule_string, config)
+}
 
-    let form = multipart::Form::new()
-        .text("title", "Bin Reminder")
-        .text("message", message)
-        .text("priority", "5");
+fn get_bin(client: Client, config: &Config, cli: &Cli) -> Result<Upcoming, Box<dyn Error>> {
+    let schedule = fetch_schedule(client, config, cli)?;
+    Ok(get_upcoming_bins(schedule, cli.start_date(), cli.days_ahead))
+}
 
-    //errors sent via notification, if ok then no issue, if error would need to send via notification anyway.
-    _ = client.post(url).multipart(form).send();
+fn notify_or_print(notifier: &dyn Notifier, message: String, config: &Config, cli: &Cli) {
+    if cli.dry_run {
+        println!("{}", message);
+    } else {
+        //errors sent via notification, if ok then no issue, if error would need to send via notification anyway.
+        _ = notifier.notify("Bin Reminder", &message, &config.notification_priority);
+    }
+}
+
+fn run(client: Client, config: &Config, cli: &Cli, notifier: &dyn Notifier) {
+    if let Some(ical_path) = &cli.ical_output {
+        match fetch_schedule(client.clone(), config, cli) {
+            Ok(schedule) => {
+                let ics = build_ical_feed(&schedule);
+                let path = if ical_path == Path::new("-") { None } else { Some(ical_path.as_path()) };
+                if let Err(e) = write_ical_feed(&ics, path) {
+                    notify_or_print(notifier, format!("Error writing ical feed: {}", e), config, cli);
+                }
+            }
+            Err(e) => notify_or_print(notifier, format!("Error: {}", e), config, cli),
+        }
+    }
+
+    match get_bin(client, config, cli) {
+        Err(e) => notify_or_print(notifier, format!("Error: {}", e), config, cli),
+        Ok(upcoming) => {
+            if let Some(message) = format_upcoming_message(&upcoming) {
+                notify_or_print(notifier, message, config, cli);
+            }
+        }
+    }
 }
 
 fn main() {
+    let cli = Cli::parse();
     let client = initiate_client().expect("Failed to create client"); //Panic on failure, as no client to send error message on
-    match get_bin(client.clone()) {
-        Err(e) => send_notification(client.clone(), format!("Error: {}", e)),
-        Ok(result) => match result {
-            Some(bin) => send_notification(client.clone(), format!("Put out {} for tomorrow", bin)),
-            None => (),
-        },
-    }
+    let config = Config::load(CONFIG_PATH).expect("Failed to load config"); //Panic on failure, as no config to read the notification URL from
+    let notifier = build_notifier(&config, client.clone());
+
+    run(client, &config, &cli, notifier.as_ref());
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use notifier::NotifierKind;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    fn test_config() -> Config {
+        Config {
+            lookup_url: "https://example.com".to_owned(),
+            address_code: "ADDR".to_owned(),
+            notification_url: "https://example.com/notify".to_owned(),
+            notification_priority: "5".to_owned(),
+            notifier: NotifierKind::Stdout,
+            bins: HashMap::from([
+                ("B".to_owned(), "Black Bin".to_owned()),
+                ("G".to_owned(), "Green Bin".to_owned()),
+                ("R".to_owned(), "Brown Bin".to_owned()),
+            ]),
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingNotifier {
+        sent: RefCell<Vec<(String, String, String)>>,
+    }
+
+    impl Notifier for RecordingNotifier {
+        fn notify(&self, title: &str, message: &str, priority: &str) -> Result<(), Box<dyn Error>> {
+            self.sent
+                .borrow_mut()
+                .push((title.to_owned(), message.to_owned(), priority.to_owned()));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_notify_or_print_sends_via_notifier() {
+        let config = test_config();
+        let cli = Cli::parse_from(["bin-reminder"]);
+        let notifier = RecordingNotifier::default();
+
+        notify_or_print(&notifier, "Put out Black Bin for tomorrow".to_owned(), &config, &cli);
+
+        let sent = notifier.sent.into_inner();
+        assert_eq!(sent, vec![(
+            "Bin Reminder".to_owned(),
+            "Put out Black Bin for tomorrow".to_owned(),
+            "5".to_owned(),
+        )]);
+    }
+
+    #[test]
+    fn test_notify_or_print_dry_run_does_not_notify() {
+        let config = test_config();
+        let cli = Cli::parse_from(["bin-reminder", "--dry-run"]);
+        let notifier = RecordingNotifier::default();
+
+        notify_or_print(&notifier, "Put out Black Bin for tomorrow".to_owned(), &config, &cli);
+
+        assert!(notifier.sent.into_inner().is_empty());
+    }
 
     #[test]
     fn test_format_bin() {
-        assert_eq!(format_bin('B'), "Black Bin".to_owned());
-        assert_eq!(format_bin('G'), "Green Bin".to_owned());
-        assert_eq!(format_bin('R'), "Brown Bin".to_owned());
-        assert_eq!(format_bin('T'), "Unknown Bin 'T'".to_owned());
+        let config = test_config();
+        assert_eq!(format_bin('B', &config), "Black Bin".to_owned());
+        assert_eq!(format_bin('G', &config), "Green Bin".to_owned());
+        assert_eq!(format_bin('R', &config), "Brown Bin".to_owned());
+        assert_eq!(format_bin('T', &config), "Unknown Bin 'T'".to_owned());
     }
 
     #[test]
     fn test_decode_date() {
         let mut response = decode_date("559H".to_owned());
-        let expected = NaiveDate::from_ymd_opt(2024, 01, 01).unwrap();
+        let expected = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
         assert_eq!(response.unwrap(), expected);
 
         response = decode_date("559I".to_owned());
@@ -173,4 +350,72 @@ mod tests {
         response = get_coded_pairs("test,abcdefghi".to_owned());
         assert!(response.is_err());
     }
+
+    #[test]
+    fn test_get_upcoming_bins() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let schedule = vec![
+            (start, "Black Bin".to_owned()),
+            (start, "Green Bin".to_owned()),
+            (start + Duration::days(1), "Brown Bin".to_owned()),
+            (start + Duration::days(5), "Black Bin".to_owned()),
+        ];
+
+        let upcoming = get_upcoming_bins(schedule, start, 2);
+        assert_eq!(
+            upcoming,
+            vec![
+                (start, vec!["Black Bin".to_owned(), "Green Bin".to_owned()]),
+                (start + Duration::days(1), vec!["Brown Bin".to_owned()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_bin_list() {
+        assert_eq!(format_bin_list(&[]), "");
+        assert_eq!(format_bin_list(&["Black Bin".to_owned()]), "Black Bin");
+        assert_eq!(
+            format_bin_list(&["Black Bin".to_owned(), "Green Bin".to_owned()]),
+            "Black Bin and Green Bin"
+        );
+        assert_eq!(
+            format_bin_list(&["Black Bin".to_owned(), "Green Bin".to_owned(), "Brown Bin".to_owned()]),
+            "Black Bin, Green Bin and Brown Bin"
+        );
+    }
+
+    #[test]
+    fn test_format_upcoming_message() {
+        assert_eq!(format_upcoming_message(&[]), None);
+
+        let tomorrow = Local::now().date_naive() + Duration::days(1);
+        let later = tomorrow + Duration::days(4);
+
+        let upcoming = vec![
+            (tomorrow, vec!["Black Bin".to_owned(), "Green Bin".to_owned()]),
+            (later, vec!["Brown Bin".to_owned()]),
+        ];
+        let message = format_upcoming_message(&upcoming).unwrap();
+        assert_eq!(
+            message,
+            format!(
+                "Put out Black Bin and Green Bin for tomorrow\nPut out Brown Bin on {}",
+                later.format("%A %-d %B")
+            )
+        );
+    }
+
+    #[test]
+    fn test_build_ical_feed() {
+        let schedule = vec![(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(), "Black Bin".to_owned())];
+        let ics = build_ical_feed(&schedule);
+
+        assert!(ics.contains("BEGIN:VEVENT"));
+        assert!(ics.contains("SUMMARY:Black Bin collection"));
+        assert!(ics.contains("20240101-Black_Bin@bin-reminder"));
+        assert!(ics.contains("BEGIN:VALARM"));
+        //-6 hours, so the alarm triggers the evening before rather than at midday
+        assert!(ics.contains("TRIGGER:-PT21600S"));
+    }
 }
@@ -0,0 +1,77 @@
+use crate::config::Config;
+use reqwest::blocking::{multipart, Client};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifierKind {
+    #[default]
+    Multipart,
+    JsonWebhook,
+    Stdout,
+}
+
+pub trait Notifier {
+    fn notify(&self, title: &str, message: &str, priority: &str) -> Result<(), Box<dyn Error>>;
+}
+
+pub struct MultipartNotifier {
+    pub client: Client,
+    pub url: String,
+}
+
+impl Notifier for MultipartNotifier {
+    fn notify(&self, title: &str, message: &str, priority: &str) -> Result<(), Box<dyn Error>> {
+        let form = multipart::Form::new()
+            .text("title", title.to_owned())
+            .text("message", message.to_owned())
+            .text("priority", priority.to_owned());
+
+        self.client.post(&self.url).multipart(form).send()?;
+        Ok(())
+    }
+}
+
+pub struct JsonWebhookNotifier {
+    pub client: Client,
+    pub url: String,
+}
+
+#[derive(Serialize)]
+struct JsonPayload<'a> {
+    title: &'a str,
+    message: &'a str,
+    priority: &'a str,
+}
+
+impl Notifier for JsonWebhookNotifier {
+    fn notify(&self, title: &str, message: &str, priority: &str) -> Result<(), Box<dyn Error>> {
+        let payload = JsonPayload { title, message, priority };
+        self.client.post(&self.url).json(&payload).send()?;
+        Ok(())
+    }
+}
+
+pub struct StdoutNotifier;
+
+impl Notifier for StdoutNotifier {
+    fn notify(&self, title: &str, message: &str, priority: &str) -> Result<(), Box<dyn Error>> {
+        println!("[{}] {}: {}", priority, title, message);
+        Ok(())
+    }
+}
+
+pub fn build_notifier(config: &Config, client: Client) -> Box<dyn Notifier> {
+    match config.notifier {
+        NotifierKind::Multipart => Box::new(MultipartNotifier {
+            client,
+            url: config.notification_url.clone(),
+        }),
+        NotifierKind::JsonWebhook => Box::new(JsonWebhookNotifier {
+            client,
+            url: config.notification_url.clone(),
+        }),
+        NotifierKind::Stdout => Box::new(StdoutNotifier),
+    }
+}